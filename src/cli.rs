@@ -1,21 +1,30 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 use crate::color::{parse_white_point, WhitePoint};
 use crate::config;
+use crate::hid::{self, parse_effect};
 
 #[derive(Parser)]
 #[command(
     version,
     about = "Set RGB color on keyboards running Vial firmware with RGB support",
     after_help = format!(
-        "Examples:\n  vialctl ff00ff\n  vialctl '#00ff00'\n  vialctl ff0000 --brightness 80\n\n\
-         Config: {}\n  Example:\n    white_point = [200, 255, 230]",
+        "Examples:\n  vialctl ff00ff\n  vialctl '#00ff00'\n  vialctl ff0000 --brightness 80\n  \
+         vialctl ff0000 --effect breathing --speed 200\n  vialctl effects\n  \
+         vialctl ff0000 --white-point 6500K\n  \
+         vialctl direct --colors ff0000,00ff00,0000ff\n  \
+         vialctl direct --gradient ff0000 0000ff --count 16\n\n\
+         Config: {}\n  Example:\n    white_point = [200, 255, 230]\n  Or: white_point = \"5000K\"",
         config::path().display()
     )
 )]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Hex color to set, e.g. ff00ff (omit when using a subcommand)
     #[arg(value_name = "HEX_COLOR")]
-    pub color: String,
+    pub color: Option<String>,
 
     #[arg(short, long)]
     pub brightness: Option<u8>,
@@ -23,6 +32,46 @@ pub struct Cli {
     #[arg(long)]
     pub no_save: bool,
 
-    #[arg(long, value_name = "R,G,B", value_parser = parse_white_point)]
+    /// White balance correction, as R,G,B (e.g. 200,255,230) or a color
+    /// temperature in Kelvin (e.g. 6500K)
+    #[arg(long, value_name = "R,G,B|KELVIN", value_parser = parse_white_point)]
     pub white_point: Option<WhitePoint>,
+
+    /// Effect to drive, by numeric ID or known name (default: solid_color)
+    #[arg(long, value_name = "ID|NAME", value_parser = parse_effect)]
+    pub effect: Option<u16>,
+
+    /// Animation speed for the chosen effect (0-255)
+    #[arg(long, default_value_t = hid::DEFAULT_EFFECT_SPEED)]
+    pub speed: u8,
+
+    /// Select a device by serial-number substring or 0-based index (see
+    /// `vialctl devices`) when more than one is attached
+    #[arg(long, value_name = "SERIAL|INDEX", global = true)]
+    pub device: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List the effect modes this keyboard reports as supported
+    Effects,
+    /// List every attached Vial RGB device
+    #[command(alias = "list")]
+    Devices,
+    /// Print the keyboard's currently active effect, speed and color
+    Get,
+    /// Set static per-LED colors via VialRGB direct mode
+    Direct {
+        /// Comma-separated hex colors, one per LED in order (e.g. ff0000,00ff00)
+        #[arg(long, value_name = "HEX,HEX,...", conflicts_with = "gradient")]
+        colors: Option<String>,
+
+        /// Two hex colors to interpolate a left-to-right gradient between
+        #[arg(long, num_args = 2, value_names = ["START", "END"], conflicts_with = "colors")]
+        gradient: Option<Vec<String>>,
+
+        /// Number of LEDs to fill when using --gradient
+        #[arg(long, requires = "gradient")]
+        count: Option<usize>,
+    },
 }
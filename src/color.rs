@@ -21,12 +21,61 @@ impl WhitePoint {
             (f64::from(b) * f64::from(wb) / 255.0).round() as u8,
         )
     }
+
+    /// Approximates a white point from a color temperature in Kelvin, using
+    /// Tanner Helland's fit to the Planckian locus. Channels are clamped to
+    /// `1..=255` (not `0`) to satisfy the `WhitePoint` invariant.
+    pub fn from_kelvin(kelvin: f64) -> Self {
+        let t = kelvin / 100.0;
+
+        let red = if t <= 66.0 {
+            255.0
+        } else {
+            329.698_727_446 * (t - 60.0).powf(-0.133_204_759_2)
+        };
+
+        let green = if t <= 66.0 {
+            99.470_802_586_1 * t.ln() - 161.119_568_166_1
+        } else {
+            288.122_169_528_3 * (t - 60.0).powf(-0.075_514_849_2)
+        };
+
+        let blue = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.517_731_223_1 * (t - 10.0).ln() - 305.044_792_730_7
+        };
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let clamp = |x: f64| {
+            if x.is_nan() {
+                1
+            } else {
+                x.round().clamp(1.0, 255.0) as u8
+            }
+        };
+
+        Self([clamp(red), clamp(green), clamp(blue)])
+    }
 }
 
 pub fn parse_white_point(s: &str) -> Result<WhitePoint, String> {
+    if let Some(digits) = s.strip_suffix(['K', 'k']) {
+        let kelvin: f64 = digits
+            .trim()
+            .parse()
+            .map_err(|e| format!("kelvin: {e}"))?;
+        return Ok(WhitePoint::from_kelvin(kelvin));
+    }
+
     let parts: Vec<&str> = s.split(',').collect();
     if parts.len() != 3 {
-        return Err("expected 3 comma-separated values, e.g. 200,255,230".into());
+        return Err(
+            "expected 3 comma-separated values (e.g. 200,255,230) or a Kelvin value (e.g. 6500K)"
+                .into(),
+        );
     }
 
     let r = parts[0]
@@ -47,7 +96,7 @@ pub fn parse_white_point(s: &str) -> Result<WhitePoint, String> {
 
 pub fn parse_hex_rgb(hex: &str) -> Result<(u8, u8, u8)> {
     let hex = hex.strip_prefix('#').unwrap_or(hex);
-    if hex.chars().count() != 6 {
+    if hex.len() != 6 || !hex.is_ascii() {
         bail!("color must be 6 hex characters (0-9, a-f), e.g. ff00ff");
     }
 
@@ -86,6 +135,60 @@ pub fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
     )
 }
 
+/// The inverse of [`rgb_to_hsv`]: converts an HSV triple (each 0-255) back
+/// to RGB.
+pub fn hsv_to_rgb(h: u8, s: u8, v: u8) -> (u8, u8, u8) {
+    let hue = f64::from(h) / 255.0 * 360.0;
+    let sat = f64::from(s) / 255.0;
+    let val = f64::from(v) / 255.0;
+
+    let c = val * sat;
+    let x = c * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = val - c;
+
+    let (r1, g1, b1) = match hue {
+        h if h < 60.0 => (c, x, 0.0),
+        h if h < 120.0 => (x, c, 0.0),
+        h if h < 180.0 => (0.0, c, x),
+        h if h < 240.0 => (0.0, x, c),
+        h if h < 300.0 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    (
+        ((r1 + m) * 255.0).round() as u8,
+        ((g1 + m) * 255.0).round() as u8,
+        ((b1 + m) * 255.0).round() as u8,
+    )
+}
+
+/// Interpolates `steps` colors between `start` and `end`, blending in HSV
+/// space (rather than RGB) so the hue sweeps smoothly instead of going
+/// through a muddy midpoint.
+pub fn hsv_gradient(start: (u8, u8, u8), end: (u8, u8, u8), steps: usize) -> Vec<(u8, u8, u8)> {
+    if steps == 0 {
+        return Vec::new();
+    }
+    if steps == 1 {
+        return vec![start];
+    }
+
+    let (h0, s0, v0) = rgb_to_hsv(start.0, start.1, start.2);
+    let (h1, s1, v1) = rgb_to_hsv(end.0, end.1, end.2);
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let lerp = |a: u8, b: u8, t: f64| (f64::from(a) + (f64::from(b) - f64::from(a)) * t).round() as u8;
+
+    (0..steps)
+        .map(|i| {
+            #[allow(clippy::cast_precision_loss)]
+            let t = i as f64 / (steps - 1) as f64;
+            hsv_to_rgb(lerp(h0, h1, t), lerp(s0, s1, t), lerp(v0, v1, t))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,6 +224,11 @@ mod tests {
         assert!(parse_hex_rgb("gghhii").is_err());
     }
 
+    #[test]
+    fn parse_hex_rgb_rejects_non_ascii_without_panicking() {
+        assert!(parse_hex_rgb("aa😀aaa").is_err());
+    }
+
     #[test]
     fn rgb_to_hsv_pure_red() {
         let (h, s, v) = rgb_to_hsv(255, 0, 0);
@@ -165,6 +273,34 @@ mod tests {
         assert_eq!(v, 128);
     }
 
+    #[test]
+    fn hsv_to_rgb_pure_red() {
+        assert_eq!(hsv_to_rgb(0, 255, 255), (255, 0, 0));
+    }
+
+    #[test]
+    fn hsv_to_rgb_black() {
+        assert_eq!(hsv_to_rgb(0, 0, 0), (0, 0, 0));
+    }
+
+    #[test]
+    fn hsv_to_rgb_white() {
+        assert_eq!(hsv_to_rgb(0, 0, 255), (255, 255, 255));
+    }
+
+    #[test]
+    fn hsv_to_rgb_roundtrips_rgb_to_hsv() {
+        // Hue is stored in 8 bits (steps of 360/255 degrees), so the
+        // secondary channel can come back up to 3 off (255,0,255 -> 255,0,252).
+        for (r, g, b) in [(255, 0, 255), (0, 255, 0), (10, 200, 90), (128, 64, 200)] {
+            let (h, s, v) = rgb_to_hsv(r, g, b);
+            let (r2, g2, b2) = hsv_to_rgb(h, s, v);
+            assert!((i16::from(r) - i16::from(r2)).abs() <= 3);
+            assert!((i16::from(g) - i16::from(g2)).abs() <= 3);
+            assert!((i16::from(b) - i16::from(b2)).abs() <= 3);
+        }
+    }
+
     #[test]
     fn white_point_rejects_zero_channel() {
         assert!(WhitePoint::new([0, 255, 255]).is_none());
@@ -219,4 +355,54 @@ mod tests {
     fn parse_white_point_rejects_overflow() {
         assert!(parse_white_point("256,255,255").is_err());
     }
+
+    #[test]
+    fn parse_white_point_kelvin() {
+        let wp = parse_white_point("6500K").unwrap();
+        // 6500K is near-neutral daylight: all channels close to full.
+        assert!(wp.0[0] > 240 && wp.0[1] > 240 && wp.0[2] > 240);
+    }
+
+    #[test]
+    fn parse_white_point_kelvin_lowercase() {
+        assert!(parse_white_point("5000k").is_ok());
+    }
+
+    #[test]
+    fn from_kelvin_warm_favors_red() {
+        let wp = WhitePoint::from_kelvin(2700.0);
+        assert!(wp.0[0] > wp.0[2]);
+    }
+
+    #[test]
+    fn from_kelvin_cool_favors_blue() {
+        let wp = WhitePoint::from_kelvin(10000.0);
+        assert!(wp.0[2] > wp.0[0]);
+    }
+
+    #[test]
+    fn from_kelvin_channels_never_zero() {
+        for kelvin in [1000.0, 1900.0, 4000.0, 15000.0] {
+            let wp = WhitePoint::from_kelvin(kelvin);
+            assert!(wp.0.iter().all(|&c| c >= 1));
+        }
+    }
+
+    #[test]
+    fn hsv_gradient_endpoints_match_inputs() {
+        let colors = hsv_gradient((255, 0, 0), (0, 0, 255), 5);
+        assert_eq!(colors.len(), 5);
+        assert_eq!(colors[0], (255, 0, 0));
+        assert_eq!(colors[4], (0, 0, 255));
+    }
+
+    #[test]
+    fn hsv_gradient_single_step_is_start() {
+        assert_eq!(hsv_gradient((10, 20, 30), (200, 100, 50), 1), vec![(10, 20, 30)]);
+    }
+
+    #[test]
+    fn hsv_gradient_zero_steps_is_empty() {
+        assert!(hsv_gradient((255, 0, 0), (0, 0, 255), 0).is_empty());
+    }
 }
@@ -3,11 +3,18 @@ use std::path::PathBuf;
 
 use serde::Deserialize;
 
-use crate::color::WhitePoint;
+use crate::color::{self, WhitePoint};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawWhitePoint {
+    Rgb([u8; 3]),
+    Expr(String),
+}
 
 #[derive(Deserialize, Default)]
 struct RawConfig {
-    white_point: Option<[u8; 3]>,
+    white_point: Option<RawWhitePoint>,
 }
 
 #[derive(Debug, Default)]
@@ -37,15 +44,20 @@ pub fn load() -> Config {
         }
     };
 
-    let white_point = raw.white_point.and_then(|rgb| {
-        let wp = WhitePoint::new(rgb);
-        if wp.is_none() {
-            eprintln!(
-                "warning: ignoring white_point in {}: channels must be 1-255",
-                path.display()
-            );
+    let white_point = raw.white_point.and_then(|value| {
+        let parsed = match value {
+            RawWhitePoint::Rgb(rgb) => {
+                WhitePoint::new(rgb).ok_or_else(|| "channels must be 1-255".to_string())
+            }
+            RawWhitePoint::Expr(s) => color::parse_white_point(&s),
+        };
+        match parsed {
+            Ok(wp) => Some(wp),
+            Err(e) => {
+                eprintln!("warning: ignoring white_point in {}: {e}", path.display());
+                None
+            }
         }
-        wp
     });
 
     Config { white_point }
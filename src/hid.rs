@@ -9,7 +9,8 @@ const MSG_LEN: usize = 32;
 
 const VIAL_SERIAL_NUMBER_MAGIC: &str = "vial:f64c2b3c";
 
-const VIALRGB_EFFECT_SOLID_COLOR: u16 = 2;
+pub const VIALRGB_EFFECT_DIRECT: u16 = 1;
+pub const VIALRGB_EFFECT_SOLID_COLOR: u16 = 2;
 
 const CMD_VIA_LIGHTING_SET_VALUE: u8 = 0x07;
 const CMD_VIA_LIGHTING_GET_VALUE: u8 = 0x08;
@@ -17,11 +18,60 @@ const CMD_VIA_LIGHTING_SAVE: u8 = 0x09;
 
 const VIALRGB_GET_INFO: u8 = 0x40;
 const VIALRGB_SET_MODE: u8 = 0x41;
+/// Same numeric value as `VIALRGB_SET_MODE`: get and set share a sub-ID,
+/// distinguished by the `CMD_VIA_LIGHTING_{GET,SET}_VALUE` command byte.
+const VIALRGB_GET_MODE: u8 = VIALRGB_SET_MODE;
 const VIALRGB_GET_SUPPORTED: u8 = 0x42;
+const VIALRGB_DIRECT_FASTSET: u8 = 0x42;
 
-const DEFAULT_EFFECT_SPEED: u8 = 128;
+pub const DEFAULT_EFFECT_SPEED: u8 = 128;
 const MAX_EFFECT_QUERY_ROUNDS: usize = 100;
 
+/// Per-LED colors per `VIALRGB_DIRECT_FASTSET` packet: the 2 command bytes,
+/// a 2-byte LED start index and 3 bytes/LED must fit in `MSG_LEN`.
+const LEDS_PER_PACKET: usize = (MSG_LEN - 4) / 3;
+
+/// Effect IDs with human-readable names, for devices that don't otherwise
+/// expose a way to name their modes. Anything not listed here is still
+/// usable by numeric ID; it is just shown by that ID, without a name.
+const KNOWN_EFFECTS: &[(u16, &str)] = &[
+    (0, "off"),
+    (1, "direct"),
+    (2, "solid_color"),
+    (3, "alternating"),
+    (4, "breathing"),
+    (5, "rainbow"),
+    (6, "cycle"),
+    (7, "snake"),
+    (8, "knight"),
+    (9, "christmas"),
+    (10, "gradient"),
+    (11, "rgb_test"),
+    (12, "twinkle"),
+];
+
+/// The human-readable name for a known effect ID, if any.
+pub fn effect_name(id: u16) -> Option<&'static str> {
+    KNOWN_EFFECTS
+        .iter()
+        .find(|(known_id, _)| *known_id == id)
+        .map(|(_, name)| *name)
+}
+
+/// Parses a `--effect` value that is either a numeric ID or a known name
+/// (case-insensitive), for use as a clap `value_parser`.
+pub fn parse_effect(s: &str) -> Result<u16, String> {
+    if let Ok(id) = s.parse::<u16>() {
+        return Ok(id);
+    }
+
+    KNOWN_EFFECTS
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(s))
+        .map(|(id, _)| *id)
+        .ok_or_else(|| format!("unknown effect '{s}' (use a numeric ID or a known name)"))
+}
+
 fn hid_send(dev: &HidDevice, msg: &[u8], attempts: u32) -> Result<[u8; MSG_LEN]> {
     ensure!(msg.len() <= MSG_LEN, "message must be <= {MSG_LEN} bytes");
 
@@ -81,14 +131,86 @@ fn is_vialrgb(api: &HidApi, info: &DeviceInfo) -> bool {
     vial_protocol >= 4 && (flags & 1) == 1
 }
 
-pub fn find_device(api: &HidApi) -> Option<&DeviceInfo> {
-    api.device_list().find(|info| {
-        let serial = info.serial_number().unwrap_or("");
-        serial.contains(VIAL_SERIAL_NUMBER_MAGIC) && is_rawhid(api, info) && is_vialrgb(api, info)
-    })
+/// Every attached device that looks like a Vial RGB keyboard.
+pub fn find_devices(api: &HidApi) -> Vec<&DeviceInfo> {
+    api.device_list()
+        .filter(|info| {
+            let serial = info.serial_number().unwrap_or("");
+            serial.contains(VIAL_SERIAL_NUMBER_MAGIC) && is_rawhid(api, info) && is_vialrgb(api, info)
+        })
+        .collect()
 }
 
-fn get_modes(dev: &HidDevice) -> Result<BTreeSet<u16>> {
+/// Resolves which Vial RGB device to talk to. With no `selector`, there
+/// must be exactly one candidate. A `selector` that parses as a number is
+/// taken as a 0-based index into [`find_devices`]'s order (with no fallback
+/// to serial matching if it is out of range); any other selector must match
+/// exactly one serial number as a substring.
+pub fn find_device<'a>(api: &'a HidApi, selector: Option<&str>) -> Result<&'a DeviceInfo> {
+    let candidates = find_devices(api);
+
+    match selector {
+        Some(selector) => select_device(&candidates, selector),
+        None => match candidates.as_slice() {
+            [] => bail!("no Vial RGB device found"),
+            [only] => Ok(*only),
+            multiple => bail!(
+                "multiple Vial RGB devices found, pass --device to pick one:\n{}",
+                describe_devices(multiple)
+            ),
+        },
+    }
+}
+
+fn select_device<'a>(candidates: &[&'a DeviceInfo], selector: &str) -> Result<&'a DeviceInfo> {
+    if let Ok(index) = selector.parse::<usize>() {
+        return candidates.get(index).copied().ok_or_else(|| {
+            anyhow::anyhow!(
+                "device index {index} out of range (0..{})",
+                candidates.len()
+            )
+        });
+    }
+
+    let matches: Vec<&'a DeviceInfo> = candidates
+        .iter()
+        .copied()
+        .filter(|info| info.serial_number().unwrap_or("").contains(selector))
+        .collect();
+
+    match matches.as_slice() {
+        [] => bail!(
+            "no device matching '{selector}' found:\n{}",
+            describe_devices(candidates)
+        ),
+        [only] => Ok(*only),
+        _ => bail!(
+            "multiple devices match '{selector}', pass --device with a more specific value:\n{}",
+            describe_devices(candidates)
+        ),
+    }
+}
+
+/// Formats candidates for `devices`/error output, one per line with their
+/// `--device` index.
+pub fn describe_devices(candidates: &[&DeviceInfo]) -> String {
+    candidates
+        .iter()
+        .enumerate()
+        .map(|(index, info)| {
+            format!(
+                "  [{index}] {} {} serial={} path={}",
+                info.manufacturer_string().unwrap_or("?"),
+                info.product_string().unwrap_or("?"),
+                info.serial_number().unwrap_or("?"),
+                info.path().to_string_lossy()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn get_modes(dev: &HidDevice) -> Result<BTreeSet<u16>> {
     let data = hid_send(dev, &[CMD_VIA_LIGHTING_GET_VALUE, VIALRGB_GET_INFO], 20)?;
     let rgb_version = u16::from_le_bytes([data[2], data[3]]);
     if rgb_version != 1 {
@@ -128,7 +250,7 @@ fn get_modes(dev: &HidDevice) -> Result<BTreeSet<u16>> {
     Ok(effects)
 }
 
-fn set_mode(dev: &HidDevice, mode: u16, speed: u8, h: u8, s: u8, v: u8) -> Result<()> {
+pub fn set_mode(dev: &HidDevice, mode: u16, speed: u8, h: u8, s: u8, v: u8) -> Result<()> {
     let mut msg = [0u8; 8];
     msg[0] = CMD_VIA_LIGHTING_SET_VALUE;
     msg[1] = VIALRGB_SET_MODE;
@@ -141,26 +263,88 @@ fn set_mode(dev: &HidDevice, mode: u16, speed: u8, h: u8, s: u8, v: u8) -> Resul
     Ok(())
 }
 
-fn save(dev: &HidDevice) -> Result<()> {
+/// The effect, speed and HSV color the keyboard currently has active.
+pub fn get_mode(dev: &HidDevice) -> Result<(u16, u8, u8, u8, u8)> {
+    let data = hid_send(dev, &[CMD_VIA_LIGHTING_GET_VALUE, VIALRGB_GET_MODE], 20)?;
+    let mode = u16::from_le_bytes([data[2], data[3]]);
+    let speed = data[4];
+    let (h, s, v) = (data[5], data[6], data[7]);
+    Ok((mode, speed, h, s, v))
+}
+
+pub fn save(dev: &HidDevice) -> Result<()> {
     hid_send(dev, &[CMD_VIA_LIGHTING_SAVE], 20)?;
     Ok(())
 }
 
-pub fn set_solid_color(dev: &HidDevice, h: u8, s: u8, v: u8, persist: bool) -> Result<()> {
+/// Sets `effect` (any mode the keyboard reports as supported, not just solid
+/// color) at the given `speed`, using `h`/`s`/`v` as the effect's color where
+/// applicable, and optionally persists it to EEPROM.
+pub fn set_effect(
+    dev: &HidDevice,
+    effect: u16,
+    speed: u8,
+    h: u8,
+    s: u8,
+    v: u8,
+    persist: bool,
+) -> Result<()> {
+    let modes = get_modes(dev)?;
+    ensure!(
+        modes.contains(&effect),
+        "keyboard doesn't support effect {effect} (run `vialctl effects` to see what it supports)"
+    );
+
+    set_mode(dev, effect, speed, h, s, v)?;
+
+    if persist {
+        save(dev)?;
+    }
+
+    Ok(())
+}
+
+/// Streams per-LED RGB colors to the keyboard via `VIALRGB_DIRECT_FASTSET`,
+/// paginating into as many `MSG_LEN` packets as needed. The keyboard must
+/// already be in direct mode (see [`set_direct`]) for these to take
+/// visible effect.
+pub fn set_direct_colors(dev: &HidDevice, colors: &[(u8, u8, u8)]) -> Result<()> {
+    for (page, chunk) in colors.chunks(LEDS_PER_PACKET).enumerate() {
+        let led_start = u16::try_from(page * LEDS_PER_PACKET)
+            .map_err(|_| anyhow::anyhow!("too many LEDs for a single direct-mode update"))?;
+
+        let mut msg = [0u8; MSG_LEN];
+        msg[0] = CMD_VIA_LIGHTING_SET_VALUE;
+        msg[1] = VIALRGB_DIRECT_FASTSET;
+        msg[2..4].copy_from_slice(&led_start.to_le_bytes());
+        for (i, &(r, g, b)) in chunk.iter().enumerate() {
+            let offset = 4 + i * 3;
+            msg[offset] = r;
+            msg[offset + 1] = g;
+            msg[offset + 2] = b;
+        }
+        hid_send(dev, &msg, 20)?;
+    }
+
+    Ok(())
+}
+
+/// Switches to VialRGB direct mode and streams `colors` (one RGB triple per
+/// LED, in LED index order).
+///
+/// `CMD_VIA_LIGHTING_SAVE` only persists mode/speed/HSV, not the streamed
+/// direct-mode buffer, so `persist` here only saves the mode switch itself;
+/// saving without also re-streaming colors on every boot would have the
+/// keyboard wake up in direct mode with every LED off.
+pub fn set_direct(dev: &HidDevice, colors: &[(u8, u8, u8)], persist: bool) -> Result<()> {
     let modes = get_modes(dev)?;
     ensure!(
-        modes.contains(&VIALRGB_EFFECT_SOLID_COLOR),
-        "keyboard doesn't support solid color effect"
+        modes.contains(&VIALRGB_EFFECT_DIRECT),
+        "keyboard doesn't support direct mode (run `vialctl effects` to see what it supports)"
     );
 
-    set_mode(
-        dev,
-        VIALRGB_EFFECT_SOLID_COLOR,
-        DEFAULT_EFFECT_SPEED,
-        h,
-        s,
-        v,
-    )?;
+    set_mode(dev, VIALRGB_EFFECT_DIRECT, DEFAULT_EFFECT_SPEED, 0, 0, 0)?;
+    set_direct_colors(dev, colors)?;
 
     if persist {
         save(dev)?;